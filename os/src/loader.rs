@@ -0,0 +1,67 @@
+//! Loading user applications into memory
+//!
+//! For chapters before ch3, user applications are simply part of the kernel
+//! image, so we load them into memory when the kernel starts. With the
+//! introduction of `fork`/`exec`, user programs may also need to be looked up
+//! by name at runtime, which is what [`get_app_data_by_name`] is for.
+
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// Get the total number of applications linked into the kernel image
+pub fn get_num_app() -> usize {
+    extern "C" {
+        fn _num_app();
+    }
+    unsafe { (_num_app as usize as *const usize).read_volatile() }
+}
+
+/// Get the raw ELF data of the app with the given index
+pub fn get_app_data(app_id: usize) -> &'static [u8] {
+    extern "C" {
+        fn _num_app();
+    }
+    let num_app_ptr = _num_app as usize as *const usize;
+    let num_app = get_num_app();
+    let app_start = unsafe { core::slice::from_raw_parts(num_app_ptr.add(1), num_app + 1) };
+    assert!(app_id < num_app);
+    unsafe {
+        core::slice::from_raw_parts(
+            app_start[app_id] as *const u8,
+            app_start[app_id + 1] - app_start[app_id],
+        )
+    }
+}
+
+lazy_static! {
+    static ref APP_NAMES: Vec<&'static str> = {
+        let num_app = get_num_app();
+        extern "C" {
+            fn _app_names();
+        }
+        let mut start = _app_names as usize as *const u8;
+        let mut v = Vec::with_capacity(num_app);
+        unsafe {
+            for _ in 0..num_app {
+                let mut end = start;
+                while end.read_volatile() != b'\0' {
+                    end = end.add(1);
+                }
+                let slice = core::slice::from_raw_parts(start, end as usize - start as usize);
+                v.push(core::str::from_utf8(slice).unwrap());
+                start = end.add(1);
+            }
+        }
+        v
+    };
+}
+
+/// Get the raw ELF data of the app with the given name, used by
+/// `sys_exec`/`sys_spawn` to look up a new program by path and by
+/// [`crate::task::INITPROC`] to find the init process
+pub fn get_app_data_by_name(name: &str) -> Option<&'static [u8]> {
+    let num_app = get_num_app();
+    (0..num_app)
+        .find(|&i| APP_NAMES[i] == name)
+        .map(get_app_data)
+}
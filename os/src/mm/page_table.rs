@@ -0,0 +1,226 @@
+//! Implementation of [`PageTable`] and its associated operations, plus a
+//! user-memory access layer (`translated_byte_buffer`/`copy_to_user`/
+//! `copy_from_user`) used by syscalls that take user pointers.
+use crate::mm::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use bitflags::*;
+
+bitflags! {
+    /// page table entry flags
+    pub struct PTEFlags: u8 {
+        const V = 1 << 0;
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+        const G = 1 << 5;
+        const A = 1 << 6;
+        const D = 1 << 7;
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+/// page table entry structure
+pub struct PageTableEntry {
+    /// bits of page table entry
+    pub bits: usize,
+}
+
+impl PageTableEntry {
+    /// create a new page table entry
+    pub fn new(ppn: PhysPageNum, flags: PTEFlags) -> Self {
+        PageTableEntry {
+            bits: ppn.0 << 10 | flags.bits as usize,
+        }
+    }
+    /// create an empty page table entry
+    pub fn empty() -> Self {
+        PageTableEntry { bits: 0 }
+    }
+    /// get the physical page number of this entry
+    pub fn ppn(&self) -> PhysPageNum {
+        (self.bits >> 10 & ((1usize << 44) - 1)).into()
+    }
+    /// get the flags of this entry
+    pub fn flags(&self) -> PTEFlags {
+        PTEFlags::from_bits(self.bits as u8).unwrap()
+    }
+    /// whether this entry is valid
+    pub fn is_valid(&self) -> bool {
+        (self.flags() & PTEFlags::V) != PTEFlags::empty()
+    }
+    /// whether this entry is readable
+    pub fn readable(&self) -> bool {
+        (self.flags() & PTEFlags::R) != PTEFlags::empty()
+    }
+    /// whether this entry is writable
+    pub fn writable(&self) -> bool {
+        (self.flags() & PTEFlags::W) != PTEFlags::empty()
+    }
+    /// whether this entry is executable
+    pub fn executable(&self) -> bool {
+        (self.flags() & PTEFlags::X) != PTEFlags::empty()
+    }
+}
+
+/// a three-level sv39 page table, owning (through `FrameTracker`) every
+/// frame used for its directories; the frames `map` points entries at are
+/// not owned by it
+pub struct PageTable {
+    root_ppn: PhysPageNum,
+    frames: Vec<FrameTracker>,
+}
+
+impl PageTable {
+    /// create a new, empty page table, with a freshly allocated root frame
+    pub fn new() -> Self {
+        let frame = frame_alloc().unwrap();
+        PageTable {
+            root_ppn: frame.ppn,
+            frames: vec![frame],
+        }
+    }
+    /// create a temporary page table handle from an already-built `satp`
+    /// token, borrowing its frames (no `FrameTracker`s, since it does not
+    /// own them -- dropping this handle must not free anything)
+    pub fn from_token(satp: usize) -> Self {
+        Self {
+            root_ppn: PhysPageNum::from(satp & ((1usize << 44) - 1)),
+            frames: Vec::new(),
+        }
+    }
+    fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate() {
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                let frame = frame_alloc().unwrap();
+                *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
+                self.frames.push(frame);
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+    fn find_pte(&self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate() {
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                return None;
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+    /// map `vpn` to `ppn` with the given flags
+    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+    /// unmap `vpn`
+    pub fn unmap(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
+        *pte = PageTableEntry::empty();
+    }
+    /// look up the page table entry mapping `vpn`, if any
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.find_pte(vpn).map(|pte| *pte)
+    }
+    /// translate a `VirtAddr` all the way down to a `PhysAddr`
+    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
+        self.find_pte(va.floor()).map(|pte| {
+            let aligned_pa: PhysAddr = pte.ppn().into();
+            let offset = va.page_offset();
+            (aligned_pa.0 + offset).into()
+        })
+    }
+    /// the `satp` CSR value (mode 8 = sv39) pointing at this page table
+    pub fn token(&self) -> usize {
+        8usize << 60 | self.root_ppn.0
+    }
+}
+
+/// Walk the page table rooted at `token` and return the (possibly several)
+/// physical byte slices covering the user-space range `[ptr, ptr + len)`,
+/// splitting at every page boundary the range straddles.
+pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr as usize;
+    let end = start + len;
+    let mut v = Vec::new();
+    while start < end {
+        let start_va = VirtAddr::from(start);
+        let vpn = start_va.floor();
+        let ppn = page_table.translate(vpn).unwrap().ppn();
+        let next_vpn = VirtPageNum(vpn.0 + 1);
+        let mut end_va: VirtAddr = next_vpn.into();
+        end_va = end_va.min(VirtAddr::from(end));
+        if end_va.page_offset() == 0 {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
+        } else {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+        }
+        start = end_va.into();
+    }
+    v
+}
+
+/// Serialize `*value` byte-by-byte into the `[dst, dst + size_of::<T>())`
+/// range of user memory addressed by `token`, transparently handling any
+/// number of page boundaries the value straddles.
+pub fn copy_to_user<T: 'static + Copy>(token: usize, dst: *mut T, value: &T) {
+    let size = core::mem::size_of::<T>();
+    let src = unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size) };
+    let mut offset = 0;
+    for chunk in translated_byte_buffer(token, dst as *const u8, size) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&src[offset..offset + len]);
+        offset += len;
+    }
+}
+
+/// Deserialize a `T` out of the `[src, src + size_of::<T>())` range of user
+/// memory addressed by `token`, transparently handling any number of page
+/// boundaries the value straddles.
+pub fn copy_from_user<T: 'static + Copy>(token: usize, src: *const T) -> T {
+    let size = core::mem::size_of::<T>();
+    let mut buf = Vec::with_capacity(size);
+    for chunk in translated_byte_buffer(token, src as *const u8, size) {
+        buf.extend_from_slice(chunk);
+    }
+    unsafe { (buf.as_ptr() as *const T).read_unaligned() }
+}
+
+/// Read a NUL-terminated string out of user memory addressed by `token`,
+/// used for the `*const u8` path arguments of `sys_exec`/`sys_spawn`.
+pub fn translated_str(token: usize, ptr: *const u8) -> String {
+    let mut string = String::new();
+    let mut va = ptr as usize;
+    loop {
+        let ch = translated_byte_buffer(token, va as *const u8, 1)[0][0];
+        if ch == 0 {
+            break;
+        }
+        string.push(ch as char);
+        va += 1;
+    }
+    string
+}
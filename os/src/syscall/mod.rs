@@ -0,0 +1,73 @@
+//! Implementation of syscalls
+//!
+//! The single entry point to all system calls, [`syscall()`], is called
+//! whenever userspace performs an `ecall`. Individual syscalls live in
+//! [`process`], named `sys_` then the syscall name.
+
+mod process;
+
+use crate::config::MAX_SYSCALL_NUM;
+use crate::task::{current_task, exit_current_and_run_next, SeccompVerdict};
+use process::*;
+
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_SECCOMP: usize = 411;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 412;
+
+/// Linux-style `EPERM` ("operation not permitted"), returned negated when a
+/// seccomp filter denies a syscall with the `Errno` action.
+const EPERM: isize = 1;
+
+/// handle syscall exception with `syscall_id` and other arguments, first
+/// counting the call in the current task's `syscall_times` and consulting
+/// its seccomp filter (if any) to decide whether the call is allowed to run
+/// at all
+pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+    let verdict = {
+        let task = current_task().unwrap();
+        let mut inner = task.inner_exclusive_access();
+        if syscall_id < MAX_SYSCALL_NUM {
+            inner.syscall_times[syscall_id] += 1;
+        }
+        let verdict = inner.seccomp_check(syscall_id);
+        verdict
+    };
+    match verdict {
+        SeccompVerdict::Allow => {}
+        SeccompVerdict::Errno => return -EPERM,
+        SeccompVerdict::Kill => {
+            exit_current_and_run_next(-1);
+            return -1;
+        }
+    }
+    match syscall_id {
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_SBRK => sys_sbrk(args[0] as i32),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        SYSCALL_SECCOMP => sys_seccomp(args[0], args[1] as *const u64),
+        SYSCALL_SCHED_SETSCHEDULER => sys_sched_setscheduler(args[0], args[1]),
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
@@ -1,15 +1,19 @@
 //! Process management syscalls
 use crate::{
     config::{MAX_SYSCALL_NUM, PAGE_SIZE},
+    loader::get_app_data_by_name,
     task::{
-        change_program_brk, exit_current_and_run_next, suspend_current_and_run_next, current_user_token, current_insert_area, current_shrink_area, get_current_task_syscall_times, get_current_task_time, TaskStatus,
+        add_task, change_program_brk, current_task, current_user_token, exit_current_and_run_next,
+        suspend_current_and_run_next, current_insert_area, current_remove_area,
+        get_current_task_syscall_times, get_current_task_time, waitpid, SchedPolicy,
+        SeccompAction, TaskStatus,
     },
     timer::get_time_us,
-    mm::{VirtAddr, PageTable, MapPermission},
+    mm::{copy_from_user, copy_to_user, translated_str, VirtAddr, PageTable, MapPermission},
 };
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct TimeVal {
     pub sec: usize,
     pub usec: usize,
@@ -17,6 +21,7 @@ pub struct TimeVal {
 
 /// Task information
 #[allow(dead_code)]
+#[derive(Clone, Copy)]
 pub struct TaskInfo {
     /// Task status in it's life cycle
     status: TaskStatus,
@@ -27,9 +32,9 @@ pub struct TaskInfo {
 }
 
 /// task exits and submit an exit code
-pub fn sys_exit(_exit_code: i32) -> ! {
+pub fn sys_exit(exit_code: i32) -> ! {
     trace!("kernel: sys_exit");
-    exit_current_and_run_next();
+    exit_current_and_run_next(exit_code);
     panic!("Unreachable in sys_exit!");
 }
 
@@ -40,55 +45,28 @@ pub fn sys_yield() -> isize {
     0
 }
 
-/// YOUR JOB: get time with second and microsecond
-/// HINT: You might reimplement it with virtual memory management.
-/// HINT: What if [`TimeVal`] is splitted by two pages ?
+/// get time with second and microsecond
 pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
     trace!("kernel: sys_get_time");
-    let pt = PageTable::from_token(current_user_token());
-
-    let va1 = VirtAddr(ts as usize);
-    let ppn1 = pt.translate(va1.floor()).unwrap().ppn();
-    let pa1 = (ppn1.0 << 12) + va1.page_offset();
-
-    let va2 = VirtAddr((ts as usize) + 8);
-    let ppn2 = pt.translate(va2.floor()).unwrap().ppn();
-    let pa2 = (ppn2.0 << 12) + va2.page_offset();
-
-    let pa1 = pa1 as *mut usize;
-    let pa2 = pa2 as *mut usize;
-
     let us = get_time_us();
-
-    unsafe {
-        *pa1 = us / 1_000_000;
-        *pa2 = us % 1_000_000;
-    }
+    let time_val = TimeVal {
+        sec: us / 1_000_000,
+        usec: us % 1_000_000,
+    };
+    copy_to_user(current_user_token(), ts, &time_val);
     0
 }
 
-/// YOUR JOB: Finish sys_task_info to pass testcases
-/// HINT: You might reimplement it with virtual memory management.
-/// HINT: What if [`TaskInfo`] is splitted by two pages ?
+/// fill in task status, per-syscall call counts and running time for the
+/// current task
 pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
-    trace!("kernel: sys_task_info NOT IMPLEMENTED YET!");
-
-    let task_syscall_times = get_current_task_syscall_times();
-    let task_time = get_current_task_time();
-
-    let va = VirtAddr(ti as usize);
-    let pt = PageTable::from_token(current_user_token());
-    let ppn = pt.translate(va.floor()).unwrap().ppn();
-    let pa = (ppn.0 << 12) + va.page_offset();
-    let pa = pa as *mut TaskInfo;
-
-    unsafe { 
-        *pa = TaskInfo {
-            status: TaskStatus::Running,
-            syscall_times: task_syscall_times, 
-            time: task_time,
-        };  
-    }
+    trace!("kernel: sys_task_info");
+    let task_info = TaskInfo {
+        status: TaskStatus::Running,
+        syscall_times: get_current_task_syscall_times(),
+        time: get_current_task_time(),
+    };
+    copy_to_user(current_user_token(), ti, &task_info);
     0
 }
 
@@ -152,3 +130,137 @@ pub fn sys_sbrk(size: i32) -> isize {
         -1
     }
 }
+
+/// set the scheduling priority of the current process; `prio` must be `>= 2`
+/// (a smaller `prio` yields a larger stride `pass`, so the task is
+/// dispatched less often), returns `prio` on success or -1 on an invalid
+/// `prio`
+pub fn sys_set_priority(prio: isize) -> isize {
+    trace!("kernel: sys_set_priority");
+    if prio < 2 {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.prio = prio as usize;
+    inner.update_pass();
+    prio
+}
+
+/// get pid of the current process
+pub fn sys_getpid() -> isize {
+    trace!("kernel: sys_getpid");
+    current_task().unwrap().getpid() as isize
+}
+
+/// fork the current process, duplicating its address space; returns the
+/// child's pid in the parent and 0 in the child
+pub fn sys_fork() -> isize {
+    trace!("kernel: sys_fork");
+    let current_task = current_task().unwrap();
+    let new_task = current_task.fork();
+    let new_pid = new_task.getpid();
+    // modify trap context of new_task, because it returns immediately after
+    // switching: for child process, fork returns 0
+    let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
+    trap_cx.x[10] = 0;
+    // add new task to scheduler
+    add_task(new_task);
+    new_pid as isize
+}
+
+/// replace the current process' address space with the ELF found at `path`
+pub fn sys_exec(path: *const u8) -> isize {
+    trace!("kernel: sys_exec");
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    if let Some(data) = get_app_data_by_name(path.as_str()) {
+        let task = current_task().unwrap();
+        task.exec(data);
+        0
+    } else {
+        -1
+    }
+}
+
+/// wait for a zombie child, `pid == -1` matches any child; writes the exit
+/// code to `exit_code_ptr` and returns the reaped pid, `-1` if no matching
+/// child exists, or `-2` if one exists but has not exited yet
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+    trace!("kernel: sys_waitpid");
+    waitpid(pid, exit_code_ptr)
+}
+
+/// spawn a new child process running the ELF found at `path`, allocating its
+/// address space directly from the ELF instead of the fork-then-exec copy
+pub fn sys_spawn(path: *const u8) -> isize {
+    trace!("kernel: sys_spawn");
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    if let Some(data) = get_app_data_by_name(path.as_str()) {
+        let new_task = current_task().unwrap().spawn(data);
+        let new_pid = new_task.getpid();
+        add_task(new_task);
+        new_pid as isize
+    } else {
+        -1
+    }
+}
+
+/// install a seccomp-style syscall filter on the current task (and, from
+/// then on, its `fork`/`spawn` children): `action` selects the default
+/// applied to any syscall not set in the `MAX_SYSCALL_NUM`-bit bitmap at
+/// `syscall_bitmap_ptr` (`0` = Allow, `1` = Kill, `2` = Errno). Returns 0 on
+/// success, or -1 if `action` is invalid or the filter would loosen one
+/// already installed -- a sandbox can only ever be tightened.
+pub fn sys_seccomp(action: usize, syscall_bitmap_ptr: *const u64) -> isize {
+    trace!("kernel: sys_seccomp");
+    let default_action = match action {
+        0 => SeccompAction::Allow,
+        1 => SeccompAction::Kill,
+        2 => SeccompAction::Errno,
+        _ => return -1,
+    };
+    let token = current_user_token();
+    let words = (MAX_SYSCALL_NUM + u64::BITS as usize - 1) / u64::BITS as usize;
+    let mut allowed = [false; MAX_SYSCALL_NUM];
+    for word in 0..words {
+        let bits: u64 = copy_from_user(token, unsafe { syscall_bitmap_ptr.add(word) });
+        for bit in 0..u64::BITS as usize {
+            let id = word * u64::BITS as usize + bit;
+            if id < MAX_SYSCALL_NUM {
+                allowed[id] = bits & (1 << bit) != 0;
+            }
+        }
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.install_seccomp(default_action, allowed) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// record the current task's scheduling class and parameter: `policy` is
+/// `0` = FIFO (no `param`), `1` = round-robin (`param` is a timer-tick
+/// quantum, `>= 1`), `2` = stride (`param` is a priority, `>= 2`, same
+/// constraint as `sys_set_priority`). Only honored by the active scheduler
+/// when it matches `policy`; returns 0 on success or -1 on an invalid
+/// `policy`/`param`.
+pub fn sys_sched_setscheduler(policy: usize, param: usize) -> isize {
+    trace!("kernel: sys_sched_setscheduler");
+    let policy = match policy {
+        0 => SchedPolicy::Fifo,
+        1 => SchedPolicy::RoundRobin,
+        2 => SchedPolicy::Stride,
+        _ => return -1,
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.set_sched_policy(policy, param) {
+        0
+    } else {
+        -1
+    }
+}
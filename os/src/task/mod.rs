@@ -0,0 +1,204 @@
+//! Task management implementation
+//!
+//! Everything about task management, like starting and switching tasks, is
+//! implemented here.
+//!
+//! A single global instance of [`Processor`] called `PROCESSOR` monitors
+//! running tasks, while a single global instance of [`TaskManager`] called
+//! `TASK_MANAGER` holds all tasks that are ready to run. A single global
+//! instance of `PidAllocator` called `PID_ALLOCATOR` allocates pid for user
+//! apps. [`run_tasks()`] is the core of the scheduler: it is called from
+//! `rust_main()` and loops forever, dispatching ready tasks.
+
+mod context;
+mod manager;
+mod pid;
+mod processor;
+mod scheduler;
+#[allow(clippy::module_inception)]
+mod switch;
+#[allow(clippy::module_inception)]
+mod task;
+
+use crate::config::MAX_SYSCALL_NUM;
+use crate::loader::get_app_data_by_name;
+use crate::mm::{MapPermission, VirtAddr};
+use alloc::sync::Arc;
+use lazy_static::*;
+use task::TaskControlBlock;
+
+pub use context::TaskContext;
+pub use manager::add_task;
+pub use pid::{pid_alloc, KernelStack, PidHandle};
+pub use processor::{
+    current_task, current_trap_cx, current_user_token, run_tasks, schedule, take_current_task,
+};
+pub use scheduler::SchedPolicy;
+pub use task::{SeccompAction, SeccompVerdict, TaskStatus};
+
+/// suspend the current 'Running' task and run the next task in task list
+pub fn suspend_current_and_run_next() {
+    // There must be an application running.
+    let task = take_current_task().unwrap();
+
+    // ---- access current TCB exclusively
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    // Change status to Ready
+    task_inner.task_status = TaskStatus::Ready;
+    drop(task_inner);
+    // ---- release current PCB
+
+    // push back to ready queue.
+    add_task(task);
+    // jump to scheduling cycle
+    schedule(task_cx_ptr);
+}
+
+/// exit the current 'Running' task with the given exit code, hand its
+/// children off to the init process, and turn it into a zombie so a parent's
+/// `waitpid` can later reap it
+pub fn exit_current_and_run_next(exit_code: i32) {
+    // take from Processor
+    let task = take_current_task().unwrap();
+
+    // **** access current TCB exclusively
+    let mut inner = task.inner_exclusive_access();
+    // Change status to Zombie
+    inner.task_status = TaskStatus::Zombie;
+    // Record exit code
+    inner.exit_code = exit_code;
+    // do not move to its parent but under initproc
+
+    // ++++++ access initproc TCB exclusively
+    {
+        let mut initproc_inner = INITPROC.inner_exclusive_access();
+        for child in inner.children.iter() {
+            child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+            initproc_inner.children.push(child.clone());
+        }
+    }
+    // ++++++ release parent PCB
+
+    inner.children.clear();
+    // deallocate user space
+    inner.memory_set.recycle_data_pages();
+    drop(inner);
+    // **** release current PCB
+    // drop task manually to maintain rc correctly
+    drop(task);
+    // we do not have to save task context
+    let mut _unused = TaskContext::zero_init();
+    schedule(&mut _unused as *mut _);
+}
+
+lazy_static! {
+    /// Creation of initial process
+    ///
+    /// the name "initproc" may be changed to other app name with "Rust" as
+    /// the source code, for example, we can change it to "main_course"
+    pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new(TaskControlBlock::new(
+        get_app_data_by_name("initproc").unwrap()
+    ));
+}
+
+/// add init process to the manager
+pub fn add_initproc() {
+    add_task(INITPROC.clone());
+}
+
+/// count down the current task's round-robin quantum by one timer tick,
+/// returning whether it has just been exhausted; meant to be called from the
+/// timer interrupt handler on every tick, immediately before it decides
+/// whether to call `suspend_current_and_run_next`
+pub fn current_task_tick() -> bool {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.remaining_quantum == 0 {
+        return false;
+    }
+    inner.remaining_quantum -= 1;
+    inner.remaining_quantum == 0
+}
+
+/// get the syscall call counts of the current task
+pub fn get_current_task_syscall_times() -> [u32; MAX_SYSCALL_NUM] {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .syscall_times
+}
+
+/// get how long the current task has been running, in ms, since it was
+/// first scheduled
+pub fn get_current_task_time() -> usize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    match inner.first_run_time {
+        Some(start) => crate::timer::get_time_ms() - start,
+        None => 0,
+    }
+}
+
+/// map `[start, end)` into the current task's address space with the given
+/// permission; used by `sys_mmap`
+pub fn current_insert_area(start: VirtAddr, end: VirtAddr, perm: MapPermission) {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .insert_framed_area(start, end, perm);
+}
+
+/// unmap `[start, end)` from the current task's address space; used by
+/// `sys_munmap`
+pub fn current_remove_area(start: VirtAddr, end: VirtAddr) {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .remove_area(start, end);
+}
+
+/// change the current task's program break by `size` bytes, returning the
+/// previous break
+pub fn change_program_brk(size: i32) -> Option<usize> {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.program_brk_change(size)
+}
+
+/// reap an exited child, by pid (or any child when `pid == -1`), writing
+/// its exit code through `current_task`'s address space at `exit_code_ptr`
+///
+/// returns the reaped child's pid, `-1` if no matching child exists at all,
+/// or `-2` if a matching child exists but has not exited yet
+pub fn waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+    let task = current_task().unwrap();
+
+    // find a child process
+    let mut inner = task.inner_exclusive_access();
+    if !inner
+        .children
+        .iter()
+        .any(|p| pid == -1 || pid as usize == p.getpid())
+    {
+        return -1;
+    }
+    let pair = inner.children.iter().enumerate().find(|(_, p)| {
+        p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
+    });
+    if let Some((idx, _)) = pair {
+        let child = inner.children.remove(idx);
+        // confirm that child will be deallocated after removing from children list
+        assert_eq!(Arc::strong_count(&child), 1);
+        let found_pid = child.getpid();
+        let exit_code = child.inner_exclusive_access().exit_code;
+        if !exit_code_ptr.is_null() {
+            crate::mm::copy_to_user(inner.get_user_token(), exit_code_ptr, &exit_code);
+        }
+        found_pid as isize
+    } else {
+        -2
+    }
+}
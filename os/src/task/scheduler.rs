@@ -0,0 +1,184 @@
+//! Pluggable ready-queue disciplines behind the [`Scheduler`] trait.
+//!
+//! [`super::manager::TaskManager`] no longer hard-codes an algorithm: it
+//! holds a `Box<dyn Scheduler>` built once by [`boot_scheduler`], and every
+//! policy only needs to implement `add`/`fetch`. [`SchedPolicy`] is also
+//! recorded per task (see `TaskControlBlockInner::sched_policy`) by
+//! `sys_sched_setscheduler`, independently of which scheduler is active,
+//! mirroring how general-purpose kernels let a task ask for a scheduling
+//! class even though only one class's tasks are actually dispatched by.
+
+use super::task::{TaskControlBlock, BIG_STRIDE};
+use alloc::boxed::Box;
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+
+/// the scheduling policy a task (or the whole system) follows
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SchedPolicy {
+    /// plain first-in-first-out queue, no preemption on scheduling grounds
+    Fifo,
+    /// fixed timer quantum, the preempted task is re-enqueued at the back
+    RoundRobin,
+    /// stride scheduling, weighted by per-task priority
+    Stride,
+}
+
+/// the policy [`boot_scheduler`] builds `TASK_MANAGER`'s scheduler from;
+/// change and rebuild to switch the whole system's scheduling algorithm
+pub const BOOT_SCHED_POLICY: SchedPolicy = SchedPolicy::Stride;
+
+/// default round-robin quantum, in timer interrupts, for a task that has not
+/// called `sys_sched_setscheduler`
+pub const DEFAULT_QUANTUM: usize = 3;
+
+/// a ready-queue discipline: where `fetch`ed tasks come from, and in what order
+pub trait Scheduler {
+    /// add a task to the ready queue
+    fn add(&mut self, task: Arc<TaskControlBlock>);
+    /// take the next task to run off the ready queue, if any
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>>;
+}
+
+/// build the [`Scheduler`] selected by [`BOOT_SCHED_POLICY`]
+pub fn boot_scheduler() -> Box<dyn Scheduler> {
+    match BOOT_SCHED_POLICY {
+        SchedPolicy::Fifo => Box::new(FifoScheduler::new()),
+        SchedPolicy::RoundRobin => Box::new(RoundRobinScheduler::new()),
+        SchedPolicy::Stride => Box::new(StrideScheduler::new()),
+    }
+}
+
+/// plain FIFO ready queue
+pub struct FifoScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl FifoScheduler {
+    /// an empty FIFO scheduler
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler for FifoScheduler {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.ready_queue.pop_front()
+    }
+}
+
+/// round-robin ready queue: the ordering itself is plain FIFO, but every
+/// `fetch` (re)loads the dispatched task's quantum counter -- a task's
+/// `sched_param` when `sched_policy == RoundRobin`, else [`DEFAULT_QUANTUM`]
+/// -- which `super::current_task_tick` then counts down from the timer
+/// interrupt; once it hits zero the timer interrupt handler calls
+/// `suspend_current_and_run_next`, which re-enqueues the preempted task
+/// through `add` just like it already does for every other policy
+pub struct RoundRobinScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl RoundRobinScheduler {
+    /// an empty round-robin scheduler
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler for RoundRobinScheduler {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let task = self.ready_queue.pop_front()?;
+        let mut inner = task.inner_exclusive_access();
+        inner.remaining_quantum = if inner.sched_policy == SchedPolicy::RoundRobin {
+            inner.sched_param.max(1)
+        } else {
+            DEFAULT_QUANTUM
+        };
+        drop(inner);
+        Some(task)
+    }
+}
+
+/// A min-heap entry ordering tasks by `stride`, tolerant of `stride`
+/// wrapping around `usize::MAX`.
+///
+/// Since every task's `pass <= BIG_STRIDE / 2` (as `prio >= 2`), the gap
+/// between any two runnable strides never exceeds `BIG_STRIDE`, so `a`'s
+/// stride is considered "behind" `b`'s whenever `b.wrapping_sub(a)` falls in
+/// `(0, BIG_STRIDE]` -- this stays correct across a wraparound of either
+/// stride counter.
+struct StrideEntry {
+    stride: usize,
+    task: Arc<TaskControlBlock>,
+}
+
+impl PartialEq for StrideEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.stride == other.stride
+    }
+}
+
+impl Eq for StrideEntry {}
+
+impl PartialOrd for StrideEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StrideEntry {
+    /// `BinaryHeap` is a max-heap, so a task with a smaller (wrapping-aware)
+    /// stride must compare as `Greater` to surface at the top.
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.stride == other.stride {
+            Ordering::Equal
+        } else if other.stride.wrapping_sub(self.stride) <= BIG_STRIDE {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        }
+    }
+}
+
+/// stride-scheduled ready queue backed by a binary heap, so `fetch` is
+/// `O(log n)` instead of the `O(n)` linear scan a plain `Vec`/`VecDeque`
+/// would need to find the minimum stride
+pub struct StrideScheduler {
+    ready_queue: BinaryHeap<StrideEntry>,
+}
+
+impl StrideScheduler {
+    /// an empty stride scheduler
+    pub fn new() -> Self {
+        Self {
+            ready_queue: BinaryHeap::new(),
+        }
+    }
+}
+
+impl Scheduler for StrideScheduler {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        let stride = task.inner_exclusive_access().stride;
+        self.ready_queue.push(StrideEntry { stride, task });
+    }
+    /// take a task out of the ready queue, advancing its stride by its
+    /// `pass` (wrapping, since `stride` is allowed to wrap around `usize`)
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let StrideEntry { stride, task } = self.ready_queue.pop()?;
+        let mut inner = task.inner_exclusive_access();
+        inner.stride = stride.wrapping_add(inner.pass);
+        drop(inner);
+        Some(task)
+    }
+}
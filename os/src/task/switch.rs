@@ -0,0 +1,10 @@
+use super::TaskContext;
+use core::arch::global_asm;
+
+global_asm!(include_str!("switch.S"));
+
+extern "C" {
+    /// Switch to the context of `next_task_cx_ptr`, saving the current
+    /// context into `current_task_cx_ptr`
+    pub fn __switch(current_task_cx_ptr: *mut TaskContext, next_task_cx_ptr: *const TaskContext);
+}
@@ -0,0 +1,435 @@
+//! Implementation of [`TaskControlBlock`]
+
+use super::scheduler::SchedPolicy;
+use super::{pid_alloc, KernelStack, PidHandle, TaskContext};
+use crate::config::{MAX_SYSCALL_NUM, TRAP_CONTEXT};
+use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
+use crate::sync::UPSafeCell;
+use crate::trap::{trap_handler, TrapContext};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefMut;
+
+/// The numerator of every task's scheduling pass: a task with priority
+/// `prio` (always `>= 2`) is charged `BIG_STRIDE / prio` per dispatch, so
+/// lower priority means a bigger stride and fewer dispatches.
+pub const BIG_STRIDE: usize = 0xFFFF;
+
+/// The task control block (TCB) of a task
+pub struct TaskControlBlock {
+    // immutable
+    /// process identifier
+    pub pid: PidHandle,
+    /// kernel stack corresponding to PID
+    pub kernel_stack: KernelStack,
+    // mutable
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+/// structure containing mutable data shared by all `TaskControlBlock`s backed
+/// through `UPSafeCell`
+pub struct TaskControlBlockInner {
+    /// the physical page number of the frame where the trap context is placed
+    pub trap_cx_ppn: PhysPageNum,
+    /// application data can only appear in low addresses lower than `base_size`
+    pub base_size: usize,
+    /// saved task context
+    pub task_cx: TaskContext,
+    /// maintain the execution status of the current process
+    pub task_status: TaskStatus,
+    /// application address space
+    pub memory_set: MemorySet,
+    /// parent process of the current process, weak reference so neither side
+    /// keeps the other alive forever
+    pub parent: Option<Weak<TaskControlBlock>>,
+    /// a vector containing TCBs of children processes of the current process
+    pub children: Vec<Arc<TaskControlBlock>>,
+    /// it is set when the process calls exit or is killed by another process,
+    /// and the value of exit code is handed off to the reaping `waitpid`
+    pub exit_code: i32,
+    /// heap bottom, used in sbrk
+    pub heap_bottom: usize,
+    /// program break, used in sbrk
+    pub program_brk: usize,
+    /// count of every syscall invoked by this task
+    pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// the time (in ms) this task was first scheduled
+    pub first_run_time: Option<usize>,
+    /// scheduling stride, advanced by `pass` every time the task is dispatched
+    pub stride: usize,
+    /// scheduling priority, `pass = BIG_STRIDE / prio`, must stay `>= 2`
+    pub prio: usize,
+    /// scheduling pass, recomputed whenever `prio` changes
+    pub pass: usize,
+    /// installed syscall sandbox, if any; inherited by `fork`/`spawn`
+    pub seccomp: Option<SeccompFilter>,
+    /// scheduling class this task has asked for via `sys_sched_setscheduler`;
+    /// honored only by whichever `Scheduler` is actually active
+    pub sched_policy: SchedPolicy,
+    /// parameter paired with `sched_policy`: priority for `Stride` (mirrors
+    /// `prio`), timer-tick quantum for `RoundRobin`, unused for `Fifo`
+    pub sched_param: usize,
+    /// timer ticks left before this task's round-robin quantum is exhausted;
+    /// (re)loaded by `RoundRobinScheduler::fetch` on every dispatch, counted
+    /// down by `super::current_task_tick` from the timer interrupt
+    pub remaining_quantum: usize,
+}
+
+impl TaskControlBlockInner {
+    /// get the physical address of trap context
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+    /// get the token of the associated address space
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+    fn get_status(&self) -> TaskStatus {
+        self.task_status
+    }
+    /// recompute `pass` for the current `prio`; called whenever `prio` changes
+    pub fn update_pass(&mut self) {
+        self.pass = BIG_STRIDE / self.prio;
+    }
+    /// whether this task is a zombie, i.e. has exited but not yet been waited on
+    pub fn is_zombie(&self) -> bool {
+        self.get_status() == TaskStatus::Zombie
+    }
+    /// consult this task's seccomp filter (if any) for `syscall_id`
+    pub fn seccomp_check(&self, syscall_id: usize) -> SeccompVerdict {
+        match &self.seccomp {
+            None => SeccompVerdict::Allow,
+            Some(filter) => {
+                if syscall_id < MAX_SYSCALL_NUM && filter.allowed[syscall_id] {
+                    SeccompVerdict::Allow
+                } else {
+                    match filter.default_action {
+                        SeccompAction::Allow => SeccompVerdict::Allow,
+                        SeccompAction::Kill => SeccompVerdict::Kill,
+                        SeccompAction::Errno => SeccompVerdict::Errno,
+                    }
+                }
+            }
+        }
+    }
+    /// install (or further restrict) this task's seccomp filter; a filter
+    /// can only be tightened once installed, so this rejects (returning
+    /// `false`, leaving the existing filter untouched) any `default_action`
+    /// less strict than the existing one (`Allow < Errno < Kill`), or any
+    /// filter whose *effective* allow-set (`allowed[i] || default_action ==
+    /// Allow`) lets through a syscall the existing filter didn't -- compared
+    /// this way so tightening a previously-inert bitmap under `default:
+    /// Allow` (e.g. to `{default: Kill, allowed: {X}}`) is correctly accepted
+    /// instead of rejected for not matching bits that never mattered
+    pub fn install_seccomp(
+        &mut self,
+        default_action: SeccompAction,
+        allowed: [bool; MAX_SYSCALL_NUM],
+    ) -> bool {
+        if let Some(existing) = &self.seccomp {
+            if default_action.strictness() < existing.default_action.strictness() {
+                return false;
+            }
+            for i in 0..MAX_SYSCALL_NUM {
+                let was_allowed = existing.allowed[i] || existing.default_action == SeccompAction::Allow;
+                let now_allowed = allowed[i] || default_action == SeccompAction::Allow;
+                if now_allowed && !was_allowed {
+                    return false;
+                }
+            }
+        }
+        self.seccomp = Some(SeccompFilter {
+            default_action,
+            allowed,
+        });
+        true
+    }
+    /// record this task's requested scheduling class and parameter, applying
+    /// any side effect the active scheduler needs to honor it immediately:
+    /// `Stride`'s `param` is a priority (same constraint and effect as
+    /// `sys_set_priority`), `RoundRobin`'s `param` is a timer-tick quantum
+    /// (must be `>= 1`), `Fifo` takes no parameter. Returns `false` on an
+    /// invalid parameter, leaving the previous policy/parameter untouched.
+    pub fn set_sched_policy(&mut self, policy: SchedPolicy, param: usize) -> bool {
+        match policy {
+            SchedPolicy::Stride => {
+                if param < 2 {
+                    return false;
+                }
+                self.prio = param;
+                self.update_pass();
+            }
+            SchedPolicy::RoundRobin => {
+                if param == 0 {
+                    return false;
+                }
+            }
+            SchedPolicy::Fifo => {}
+        }
+        self.sched_policy = policy;
+        self.sched_param = param;
+        true
+    }
+}
+
+impl TaskControlBlock {
+    /// get the mutable reference of the inner TCB
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+    /// create a new parentless TCB from ELF data, used for the init process
+    /// (`sys_spawn` goes through [`Self::spawn`] instead)
+    pub fn new(elf_data: &[u8]) -> Self {
+        // memory_set with elf program headers/trampoline/trap context/user stack
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        let task_status = TaskStatus::Ready;
+        // map a kernel-stack in kernel space
+        let pid_handle = pid_alloc();
+        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack_top = kernel_stack.get_top();
+        let task_control_block = Self {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: user_sp,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status,
+                    memory_set,
+                    parent: None,
+                    children: Vec::new(),
+                    exit_code: 0,
+                    heap_bottom: user_sp,
+                    program_brk: user_sp,
+                    syscall_times: [0; MAX_SYSCALL_NUM],
+                    first_run_time: None,
+                    stride: 0,
+                    prio: 16,
+                    pass: BIG_STRIDE / 16,
+                    seccomp: None,
+                    sched_policy: SchedPolicy::Fifo,
+                    sched_param: 0,
+                    remaining_quantum: 0,
+                })
+            },
+        };
+        // prepare TrapContext in user space
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        task_control_block
+    }
+    /// replace the address space of this task in place with a freshly loaded
+    /// ELF, keeping its PID and kernel stack
+    pub fn exec(&self, elf_data: &[u8]) {
+        // memory_set with elf program headers/trampoline/trap context/user stack
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+
+        // **** access current TCB exclusively
+        let mut inner = self.inner_exclusive_access();
+        // substitute memory_set
+        inner.memory_set = memory_set;
+        // update trap_cx ppn
+        inner.trap_cx_ppn = trap_cx_ppn;
+        // initialize base_size
+        inner.base_size = user_sp;
+        inner.heap_bottom = user_sp;
+        inner.program_brk = user_sp;
+        // initialize trap_cx
+        let trap_cx = inner.get_trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            self.kernel_stack.get_top(),
+            trap_handler as usize,
+        );
+        // **** release current PCB
+    }
+    /// duplicate the current TCB into a child with a full copy of its
+    /// `MemorySet`, an identical trap context, and a freshly allocated
+    /// PID/kernel stack
+    pub fn fork(self: &Arc<TaskControlBlock>) -> Arc<TaskControlBlock> {
+        // ---- access parent PCB exclusively
+        let mut parent_inner = self.inner_exclusive_access();
+        // copy user space (including trap context)
+        let memory_set = MemorySet::from_existed_user(&parent_inner.memory_set);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        // alloc a pid and a kernel stack in kernel space
+        let pid_handle = pid_alloc();
+        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack_top = kernel_stack.get_top();
+        let task_control_block = Arc::new(TaskControlBlock {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: parent_inner.base_size,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    heap_bottom: parent_inner.heap_bottom,
+                    program_brk: parent_inner.program_brk,
+                    syscall_times: [0; MAX_SYSCALL_NUM],
+                    first_run_time: None,
+                    stride: parent_inner.stride,
+                    prio: parent_inner.prio,
+                    pass: parent_inner.pass,
+                    seccomp: parent_inner.seccomp.clone(),
+                    sched_policy: parent_inner.sched_policy,
+                    sched_param: parent_inner.sched_param,
+                    remaining_quantum: 0,
+                })
+            },
+        });
+        // add child
+        parent_inner.children.push(task_control_block.clone());
+        // modify kernel_sp in trap_cx
+        // **** access child PCB exclusively
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        trap_cx.kernel_sp = kernel_stack_top;
+        // return
+        task_control_block
+        // ---- release parent PCB
+        // **** release child PCB
+    }
+    /// allocate a fresh PID/address space directly from `elf_data`, skipping
+    /// the address-space copy `fork` followed by `exec` would otherwise pay
+    /// for; the spawned task is still registered as a child of `self` so
+    /// `waitpid` can reap it
+    pub fn spawn(self: &Arc<TaskControlBlock>, elf_data: &[u8]) -> Arc<TaskControlBlock> {
+        // ---- access parent PCB exclusively
+        let mut parent_inner = self.inner_exclusive_access();
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        let pid_handle = pid_alloc();
+        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack_top = kernel_stack.get_top();
+        let task_control_block = Arc::new(TaskControlBlock {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: user_sp,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    heap_bottom: user_sp,
+                    program_brk: user_sp,
+                    syscall_times: [0; MAX_SYSCALL_NUM],
+                    first_run_time: None,
+                    stride: parent_inner.stride,
+                    prio: parent_inner.prio,
+                    pass: parent_inner.pass,
+                    seccomp: parent_inner.seccomp.clone(),
+                    sched_policy: parent_inner.sched_policy,
+                    sched_param: parent_inner.sched_param,
+                    remaining_quantum: 0,
+                })
+            },
+        });
+        parent_inner.children.push(task_control_block.clone());
+        // **** access child PCB exclusively
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        task_control_block
+        // ---- release parent PCB
+    }
+    /// get pid of the process
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// task status: UnInit, Ready, Running, Zombie
+pub enum TaskStatus {
+    /// uninitialized
+    UnInit,
+    /// ready to run
+    Ready,
+    /// currently running
+    Running,
+    /// exited and waiting to be reaped by its parent's `waitpid`
+    Zombie,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// the action a seccomp filter takes when a syscall isn't in the allowed
+/// bitmap
+pub enum SeccompAction {
+    /// let the syscall through
+    Allow,
+    /// terminate the task, as if it had called `sys_exit`
+    Kill,
+    /// deny the syscall, returning `-EPERM` without executing it
+    Errno,
+}
+
+impl SeccompAction {
+    /// total order used by `install_seccomp`'s tighten-only check: a lower
+    /// number lets more through, so installing a lower-strictness action
+    /// over a higher one is a loosening and must be rejected
+    fn strictness(self) -> u8 {
+        match self {
+            SeccompAction::Allow => 0,
+            SeccompAction::Errno => 1,
+            SeccompAction::Kill => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// outcome of consulting a task's [`SeccompFilter`] for a given syscall id
+pub enum SeccompVerdict {
+    /// let the syscall through
+    Allow,
+    /// terminate the task
+    Kill,
+    /// deny the syscall, returning `-EPERM`
+    Errno,
+}
+
+/// an installable per-task syscall sandbox: a syscall id with `allowed[id]`
+/// set always goes through, everything else falls back to `default_action`
+#[derive(Clone)]
+pub struct SeccompFilter {
+    /// action applied to any syscall not set in `allowed`
+    pub default_action: SeccompAction,
+    /// bitmap of explicitly allowed syscall ids
+    pub allowed: [bool; MAX_SYSCALL_NUM],
+}
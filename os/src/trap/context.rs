@@ -0,0 +1,49 @@
+//! Implementation of [`TrapContext`]
+use riscv::register::sstatus::{self, Sstatus, SPP};
+
+#[repr(C)]
+/// trap context structure containing the trapped task's general-purpose
+/// registers plus everything `__restore` needs to resume it in user mode
+pub struct TrapContext {
+    /// general regs[0..31]
+    pub x: [usize; 32],
+    /// CSR sstatus
+    pub sstatus: Sstatus,
+    /// CSR sepc
+    pub sepc: usize,
+    /// token of the kernel address space
+    pub kernel_satp: usize,
+    /// top of this task's kernel stack
+    pub kernel_sp: usize,
+    /// address of `trap_handler`
+    pub trap_handler: usize,
+}
+
+impl TrapContext {
+    /// set stack pointer to x_2 reg (sp)
+    pub fn set_sp(&mut self, sp: usize) {
+        self.x[2] = sp;
+    }
+    /// build the initial trap context a freshly loaded (or freshly forked)
+    /// task resumes into on its first trip through `__restore`
+    pub fn app_init_context(
+        entry: usize,
+        sp: usize,
+        kernel_satp: usize,
+        kernel_sp: usize,
+        trap_handler: usize,
+    ) -> Self {
+        let mut sstatus = sstatus::read();
+        sstatus.set_spp(SPP::User);
+        let mut cx = Self {
+            x: [0; 32],
+            sstatus,
+            sepc: entry,
+            kernel_satp,
+            kernel_sp,
+            trap_handler,
+        };
+        cx.set_sp(sp);
+        cx
+    }
+}